@@ -0,0 +1,269 @@
+use crate::screens::{Mode, Output, Position, Resolution};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// How close a listed mode's refresh rate must be to a requested one (in Hz) to count
+/// as a match, since panels rarely advertise the exact decimal a user asks for.
+const REFRESH_TOLERANCE_HZ: f64 = 0.5;
+
+fn parse_mode_spec(spec: &str) -> Result<(Resolution, Option<f64>)> {
+    let (dimensions, refresh) = match spec.split_once('@') {
+        Some((dimensions, refresh)) => (dimensions, Some(refresh)),
+        None => (spec, None),
+    };
+    let (width, height) = dimensions.split_once('x').ok_or_else(|| {
+        anyhow::anyhow!("Invalid mode '{}', expected WIDTHxHEIGHT[@REFRESH].", spec)
+    })?;
+    let resolution = Resolution {
+        width: width
+            .parse()
+            .with_context(|| format!("Invalid width in mode '{}'.", spec))?,
+        height: height
+            .parse()
+            .with_context(|| format!("Invalid height in mode '{}'.", spec))?,
+    };
+    let refresh_hz = refresh
+        .map(|refresh| {
+            refresh
+                .parse::<f64>()
+                .with_context(|| format!("Invalid refresh rate in mode '{}'.", spec))
+        })
+        .transpose()?;
+    Ok((resolution, refresh_hz))
+}
+
+/// Resolves a `WIDTHxHEIGHT[@REFRESH]` spec against `output`'s advertised modes,
+/// preferring the listed mode whose refresh is closest to the requested one. When a
+/// refresh was given and nothing listed matches closely enough, falls back to a
+/// synthetic custom mode (which the wlr write path applies via `set_custom_mode`); this
+/// does not check whether the head accepts custom modes, so an unsupported one is only
+/// rejected by the compositor when the configuration is tested or applied. When no
+/// refresh was given and no listed mode matches the resolution
+/// at all, there's nothing sensible to synthesize (no refresh to put in it), so this
+/// returns an error listing the modes the head actually advertises.
+pub fn resolve_mode(output: &Output, spec: &str) -> Result<Mode> {
+    let (resolution, refresh_hz) = parse_mode_spec(spec)?;
+    let candidates = output
+        .modes
+        .iter()
+        .filter(|mode| mode.resolution == resolution);
+
+    let chosen = match refresh_hz {
+        Some(hz) => candidates
+            .map(|mode| (mode, (mode.refresh as f64 / 1000.0 - hz).abs()))
+            .filter(|(_, diff)| *diff <= REFRESH_TOLERANCE_HZ)
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).expect("refresh diffs are finite"))
+            .map(|(mode, _)| mode),
+        None => {
+            let candidates: Vec<&Mode> = candidates.collect();
+            candidates
+                .iter()
+                .find(|mode| mode.preferred)
+                .or_else(|| candidates.first())
+                .copied()
+        }
+    };
+
+    if let Some(mode) = chosen {
+        return Ok(*mode);
+    }
+
+    if let Some(hz) = refresh_hz {
+        return Ok(Mode {
+            resolution,
+            refresh: (hz * 1000.0).round() as i32,
+            preferred: false,
+        });
+    }
+
+    let available = output
+        .modes
+        .iter()
+        .map(|mode| mode.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    Err(anyhow::anyhow!(
+        "No mode matching '{}' on {}; available modes: {}",
+        spec,
+        output.name,
+        available
+    ))
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub struct ProfilePosition {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl From<ProfilePosition> for Position {
+    fn from(position: ProfilePosition) -> Self {
+        Position {
+            x: position.x,
+            y: position.y,
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ProfileOutput {
+    pub identity: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub position: ProfilePosition,
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    pub mode: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub outputs: Vec<ProfileOutput>,
+}
+
+impl Profile {
+    fn connected_set(&self) -> HashSet<&str> {
+        self.outputs.iter().map(|o| o.identity.as_str()).collect()
+    }
+
+    fn matches(&self, connected: &HashSet<String>) -> bool {
+        let wanted = self.connected_set();
+        wanted.len() == connected.len() && connected.iter().all(|id| wanted.contains(id.as_str()))
+    }
+
+    pub fn output(&self, identity: &str) -> Option<&ProfileOutput> {
+        self.outputs.iter().find(|o| o.identity == identity)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Config {
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read profile config at {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse profile config at {}", path.display()))
+    }
+
+    pub fn default_path() -> Result<PathBuf> {
+        if let Ok(config_home) = std::env::var("XDG_CONFIG_HOME") {
+            return Ok(PathBuf::from(config_home).join("sway-screens/profiles.toml"));
+        }
+        let home = std::env::var("HOME")
+            .context("Neither XDG_CONFIG_HOME nor HOME is set; can't locate profile config.")?;
+        Ok(PathBuf::from(home).join(".config/sway-screens/profiles.toml"))
+    }
+
+    pub fn matching<'a>(&'a self, connected: &[Output]) -> Option<&'a Profile> {
+        let connected: HashSet<String> = connected.iter().map(Output::identity).collect();
+        self.profiles.iter().find(|profile| profile.matches(&connected))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn output(identity: &str) -> ProfileOutput {
+        ProfileOutput {
+            identity: identity.into(),
+            enabled: true,
+            position: ProfilePosition::default(),
+            scale: 1.0,
+            mode: None,
+        }
+    }
+
+    #[test]
+    fn test_matches_requires_exact_connected_set() {
+        let profile = Profile {
+            name: "docked".into(),
+            outputs: vec![output("a"), output("b")],
+        };
+
+        assert!(profile.matches(&["a".into(), "b".into()].into_iter().collect()));
+        assert!(!profile.matches(&["a".into()].into_iter().collect()));
+        assert!(!profile.matches(&["a".into(), "b".into(), "c".into()].into_iter().collect()));
+    }
+
+    fn mode(width: i32, height: i32, refresh_mhz: i32, preferred: bool) -> Mode {
+        Mode {
+            resolution: Resolution { width, height },
+            refresh: refresh_mhz,
+            preferred,
+        }
+    }
+
+    fn screen(modes: Vec<Mode>) -> Output {
+        Output {
+            name: "DP-1".into(),
+            enabled: true,
+            description: String::new(),
+            make: "Denial".into(),
+            model: "of Service".into(),
+            serial: "1".into(),
+            current_mode: None,
+            preferred_mode: modes.iter().find(|m| m.preferred).copied(),
+            modes,
+            position: None,
+            scale: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_resolve_mode_picks_closest_refresh() {
+        let screen = screen(vec![
+            mode(3840, 2160, 59_940, true),
+            mode(3840, 2160, 143_890, false),
+        ]);
+
+        let resolved = resolve_mode(&screen, "3840x2160@144").unwrap();
+        assert_eq!(resolved.refresh, 143_890);
+    }
+
+    #[test]
+    fn test_resolve_mode_without_refresh_prefers_preferred_mode() {
+        let screen = screen(vec![
+            mode(1920, 1080, 60_000, true),
+            mode(1920, 1080, 75_000, false),
+        ]);
+
+        let resolved = resolve_mode(&screen, "1920x1080").unwrap();
+        assert_eq!(resolved.refresh, 60_000);
+    }
+
+    #[test]
+    fn test_resolve_mode_falls_back_to_custom_mode() {
+        let screen = screen(vec![mode(1920, 1080, 60_000, true)]);
+
+        let resolved = resolve_mode(&screen, "2560x1440@120").unwrap();
+        assert_eq!(resolved.resolution, Resolution { width: 2560, height: 1440 });
+        assert_eq!(resolved.refresh, 120_000);
+        assert!(!resolved.preferred);
+    }
+
+    #[test]
+    fn test_resolve_mode_without_refresh_errors_on_no_match() {
+        let screen = screen(vec![mode(1920, 1080, 60_000, true)]);
+
+        let err = resolve_mode(&screen, "2560x1440").unwrap_err();
+        assert!(err.to_string().contains("1920×1080"));
+    }
+}
@@ -1,30 +1,34 @@
 use anyhow::Result;
+use serde::Serialize;
 use std::fmt::{self, Debug};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub struct Position {
     pub x: i32,
     pub y: i32,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub struct Resolution {
     pub width: i32,
     pub height: i32,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
 pub struct Mode {
     pub resolution: Resolution,
     pub refresh: i32,
     pub preferred: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Output {
     pub name: String,
     pub enabled: bool,
     pub description: String,
+    pub make: String,
+    pub model: String,
+    pub serial: String,
     pub current_mode: Option<Mode>,
     pub preferred_mode: Option<Mode>,
     pub modes: Vec<Mode>,
@@ -32,6 +36,19 @@ pub struct Output {
     pub scale: f64,
 }
 
+impl Output {
+    /// Stable identity (make+model+serial) to key layout matching on instead of the
+    /// connector name, which can change across reboots or docking. Falls back to the
+    /// connector name when the head reports no serial number.
+    pub fn identity(&self) -> String {
+        if self.serial.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{} {} {}", self.make, self.model, self.serial)
+        }
+    }
+}
+
 impl fmt::Display for Output {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use colored::Colorize;
@@ -84,8 +101,30 @@ impl fmt::Display for Mode {
     }
 }
 
+/// One head's desired state within a single [`OutputManager::apply_configuration`] call.
+#[derive(Debug)]
+pub enum OutputChange<'a> {
+    Enable {
+        output: &'a Output,
+        position: Position,
+        scale: f64,
+        mode: Option<Mode>,
+    },
+    Disable {
+        output: &'a Output,
+    },
+}
+
 pub trait OutputManager {
     fn get_outputs(&self) -> Result<Vec<Output>>;
-    fn enable_output(&self, output: &Output, position: &Position) -> Result<()>;
-    fn disable_output(&self, output: &Output) -> Result<()>;
+    /// Applies every change in `changes` as a single atomic configuration: one
+    /// `create_configuration`, one `enable_head`/`disable_head` per change, one
+    /// `apply`/`test`. When `dry_run` is set, the configuration is only probed via the
+    /// compositor's `test` request: nothing is actually changed, and a `Failed`/
+    /// `Cancelled` result still surfaces as an `Err`.
+    fn apply_configuration(&self, changes: &[OutputChange], dry_run: bool) -> Result<()>;
+    /// Blocks forever, invoking `on_change` with the current outputs once up front and
+    /// again every time the compositor reports a changed configuration (hotplug, mode
+    /// change, ...).
+    fn watch<F: FnMut(Vec<Output>) -> Result<()>>(&self, on_change: F) -> Result<()>;
 }
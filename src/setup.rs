@@ -1,27 +0,0 @@
-use anyhow::Result;
-pub fn parse_setup(arg: Vec<String>) -> Result<Vec<usize>> {
-    if arg.is_empty() {
-        return Ok(vec![]);
-    }
-
-    arg[0]
-        .chars()
-        .map(|c| {
-            c.to_digit(10)
-                .map(|i| i as usize)
-                .ok_or(anyhow::anyhow!("char '{}' not a digit", c))
-        })
-        .collect::<Result<Vec<usize>>>()
-}
-
-#[cfg(test)]
-mod test {
-    use super::*;
-
-    #[test]
-    fn test_parse_setup() {
-        let args = vec![String::from("012")];
-        let result = parse_setup(args).unwrap();
-        assert_eq!(result, &[0, 1, 2]);
-    }
-}
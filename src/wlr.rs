@@ -1,26 +1,68 @@
-use crate::screens::{Mode, Output, OutputManager, Position};
+use crate::screens::{Mode, Output, OutputChange, OutputManager, Position};
 use anyhow::Result;
 use fxhash::FxHashMap;
-use tracing::{info, trace, warn};
+use std::cell::RefCell;
+use tracing::{info, trace};
 use wayland_client::backend::ObjectId;
+use wayland_client::{EventQueue, QueueHandle};
+use wayland_protocols_wlr::output_management::v1::client::{
+    zwlr_output_configuration_head_v1::ZwlrOutputConfigurationHeadV1,
+    zwlr_output_head_v1::ZwlrOutputHeadV1, zwlr_output_manager_v1::ZwlrOutputManagerV1,
+    zwlr_output_mode_v1::ZwlrOutputModeV1,
+};
 
+mod output_configuration;
 mod output_head;
 mod output_manager;
 mod output_mode;
 mod registry;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfigurationResult {
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
 #[derive(Debug)]
 struct OutputQueryState {
     running: bool,
     outputs: FxHashMap<ObjectId, crate::screens::Output>,
+    heads: FxHashMap<ObjectId, ZwlrOutputHeadV1>,
     modes: FxHashMap<ObjectId, Mode>,
+    mode_proxies: FxHashMap<ObjectId, ZwlrOutputModeV1>,
     output_to_modes: FxHashMap<ObjectId, Vec<ObjectId>>,
     outputs_current_mode: FxHashMap<ObjectId, ObjectId>,
     capabilities: Vec<String>,
     finalised_output: Vec<Output>,
+    manager: Option<ZwlrOutputManagerV1>,
+    serial: u32,
+    // Set only by the `ZwlrOutputConfigurationV1` dispatch impl (`apply_configuration`
+    // merely resets them before waiting), so a manager `Done` racing with our own apply
+    // can never be mistaken for a configuration result.
+    configuration_done: bool,
+    configuration_result: Option<ConfigurationResult>,
 }
 
 impl OutputQueryState {
+    fn new() -> Self {
+        Self {
+            running: true,
+            outputs: FxHashMap::default(),
+            heads: FxHashMap::default(),
+            capabilities: Vec::new(),
+            output_to_modes: FxHashMap::default(),
+            modes: FxHashMap::default(),
+            mode_proxies: FxHashMap::default(),
+            outputs_current_mode: FxHashMap::default(),
+            finalised_output: Vec::new(),
+            manager: None,
+            serial: 0,
+            configuration_done: false,
+            configuration_result: None,
+        }
+    }
+
     fn finalise(&mut self) {
         self.running = false;
         self.finalised_output = self
@@ -36,6 +78,9 @@ impl OutputQueryState {
             name: output.name.clone(),
             enabled: output.enabled,
             description: output.description.clone(),
+            make: output.make.clone(),
+            model: output.model.clone(),
+            serial: output.serial.clone(),
             current_mode: self.find_current_mode(id),
             preferred_mode: modes.iter().find(|mode| mode.preferred).cloned(),
             modes,
@@ -62,38 +107,74 @@ impl OutputQueryState {
             })
             .unwrap_or_default()
     }
+
+    fn find_head(&self, output: &Output) -> Result<(&ObjectId, &ZwlrOutputHeadV1)> {
+        let identity = output.identity();
+        let (id, _) = self
+            .outputs
+            .iter()
+            .find(|(_, candidate)| candidate.identity() == identity)
+            .ok_or_else(|| anyhow::anyhow!("No known output head matching '{}'.", identity))?;
+        let head = self
+            .heads
+            .get(id)
+            .ok_or_else(|| anyhow::anyhow!("Output head '{}' has no bound proxy.", identity))?;
+        Ok((id, head))
+    }
+
+    fn find_mode_proxy(&self, head_id: &ObjectId, mode: &Mode) -> Option<&ZwlrOutputModeV1> {
+        let mode_id = self
+            .output_to_modes
+            .get(head_id)?
+            .iter()
+            .find(|id| self.modes.get(*id) == Some(mode))?;
+        self.mode_proxies.get(mode_id)
+    }
+
+    fn remove_head(&mut self, id: &ObjectId) {
+        self.outputs.remove(id);
+        self.outputs_current_mode.remove(id);
+        if let Some(mode_ids) = self.output_to_modes.remove(id) {
+            for mode_id in mode_ids {
+                self.modes.remove(&mode_id);
+                self.mode_proxies.remove(&mode_id);
+            }
+        }
+        self.heads.remove(id);
+    }
+}
+
+fn set_configuration_head_mode(
+    state: &OutputQueryState,
+    head_id: &ObjectId,
+    config_head: &ZwlrOutputConfigurationHeadV1,
+    mode: &Mode,
+) {
+    match state.find_mode_proxy(head_id, mode) {
+        Some(mode_proxy) => config_head.set_mode(mode_proxy),
+        None => {
+            config_head.set_custom_mode(mode.resolution.width, mode.resolution.height, mode.refresh)
+        }
+    }
 }
 
 pub struct WlrOutputManager {
-    connection: wayland_client::Connection,
+    qh: QueueHandle<OutputQueryState>,
+    queue: RefCell<EventQueue<OutputQueryState>>,
+    state: RefCell<OutputQueryState>,
 }
 
 impl WlrOutputManager {
     pub fn new() -> Result<Self> {
-        Ok(Self {
-            connection: wayland_client::Connection::connect_to_env()?,
-        })
-    }
-}
-
-impl OutputManager for WlrOutputManager {
-    fn get_outputs(&self) -> Result<Vec<Output>> {
-        let display = self.connection.display();
-        let mut q = self.connection.new_event_queue::<OutputQueryState>();
-        let qh = q.handle();
+        let connection = wayland_client::Connection::connect_to_env()?;
+        let display = connection.display();
+        let mut queue = connection.new_event_queue::<OutputQueryState>();
+        let qh = queue.handle();
         let _registry = display.get_registry(&qh, ());
 
-        let mut state = OutputQueryState {
-            running: true,
-            outputs: FxHashMap::default(),
-            capabilities: Vec::new(),
-            output_to_modes: FxHashMap::default(),
-            modes: FxHashMap::default(),
-            outputs_current_mode: FxHashMap::default(),
-            finalised_output: Vec::new(),
-        };
+        let mut state = OutputQueryState::new();
         while state.running {
-            q.blocking_dispatch(&mut state)?;
+            queue.blocking_dispatch(&mut state)?;
         }
 
         trace!(
@@ -101,21 +182,127 @@ impl OutputManager for WlrOutputManager {
             state.capabilities
         );
 
-        info!("Found {} outputs.", state.finalised_output.len());
-
-        Ok(state.finalised_output)
+        Ok(Self {
+            qh,
+            queue: RefCell::new(queue),
+            state: RefCell::new(state),
+        })
     }
 
-    fn enable_output(&self, output: &Output, position: &Position) -> Result<()> {
-        warn!(
-            "NYI: Enabling output {} at position {:?}.",
-            output, position
-        );
+    // Re-dispatches on the manager bound in `new()` until it next reports `Done`, i.e.
+    // until the compositor reports its next output change. This never rebinds the
+    // registry or the manager, so the connection's Wayland objects are bound once for
+    // the process's life rather than per call.
+    fn refresh(&self) -> Result<()> {
+        let mut queue = self.queue.borrow_mut();
+        let mut state = self.state.borrow_mut();
+        state.running = true;
+        while state.running {
+            queue.blocking_dispatch(&mut state)?;
+        }
         Ok(())
     }
+}
 
-    fn disable_output(&self, output: &Output) -> Result<()> {
-        warn!("NYI: Disabling output {}", output);
-        Ok(())
+impl OutputManager for WlrOutputManager {
+    fn get_outputs(&self) -> Result<Vec<Output>> {
+        // A roundtrip processes whatever the compositor has already sent without waiting
+        // for a further `Done`, which would only arrive after an output change.
+        self.queue
+            .borrow_mut()
+            .roundtrip(&mut self.state.borrow_mut())?;
+        let state = self.state.borrow();
+        info!("Found {} outputs.", state.finalised_output.len());
+        Ok(state.finalised_output.clone())
+    }
+
+    fn apply_configuration(&self, changes: &[OutputChange], dry_run: bool) -> Result<()> {
+        let state = self.state.borrow();
+        let manager = state
+            .manager
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Compositor does not advertise zwlr_output_manager_v1."))?;
+        let configuration = manager.create_configuration(state.serial, &self.qh, ());
+
+        for change in changes {
+            match change {
+                OutputChange::Enable {
+                    output,
+                    position,
+                    scale,
+                    mode,
+                } => {
+                    let (head_id, head) = state.find_head(output)?;
+                    let head_id = head_id.clone();
+                    let head = head.clone();
+                    let config_head = configuration.enable_head(&head, &self.qh, ());
+                    match mode.or(output.current_mode).or(output.preferred_mode) {
+                        Some(mode) => set_configuration_head_mode(&state, &head_id, &config_head, &mode),
+                        None => {
+                            return Err(anyhow::anyhow!(
+                                "No mode to enable '{}' with: none requested, and the head reports neither a current nor a preferred mode.",
+                                output.identity()
+                            ));
+                        }
+                    }
+                    config_head.set_position(position.x, position.y);
+                    config_head.set_scale(*scale);
+                }
+                OutputChange::Disable { output } => {
+                    let (_, head) = state.find_head(output)?;
+                    let head = head.clone();
+                    configuration.disable_head(&head);
+                }
+            }
+        }
+        drop(state);
+
+        if dry_run {
+            configuration.test();
+        } else {
+            configuration.apply();
+        }
+
+        let mut queue = self.queue.borrow_mut();
+        let mut state = self.state.borrow_mut();
+        state.configuration_done = false;
+        state.configuration_result = None;
+        while !state.configuration_done {
+            queue.blocking_dispatch(&mut state)?;
+        }
+        configuration.destroy();
+
+        match state.configuration_result {
+            Some(ConfigurationResult::Succeeded) => Ok(()),
+            Some(ConfigurationResult::Failed) => {
+                Err(anyhow::anyhow!("Compositor rejected the output configuration."))
+            }
+            Some(ConfigurationResult::Cancelled) => Err(anyhow::anyhow!(
+                "Output configuration was cancelled by the compositor."
+            )),
+            None => Err(anyhow::anyhow!(
+                "Configuration dispatch loop exited without a result."
+            )),
+        }
+    }
+
+    fn watch<F: FnMut(Vec<Output>) -> Result<()>>(&self, mut on_change: F) -> Result<()> {
+        let mut last = self.state.borrow().finalised_output.clone();
+        on_change(last.clone())?;
+
+        loop {
+            self.refresh()?;
+            let current = self.state.borrow().finalised_output.clone();
+            if current == last {
+                continue;
+            }
+            // If the compositor normalizes whatever we requested (position snapping,
+            // scale rounding, ...) so the post-apply state never equals what we last
+            // saw, this fires on every Done forever. Logged so that's observable
+            // instead of a silent apply-and-loop.
+            info!("Output configuration changed; reconciling.");
+            last = current;
+            on_change(last.clone())?;
+        }
     }
 }
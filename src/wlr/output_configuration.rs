@@ -0,0 +1,43 @@
+use tracing::debug;
+use wayland_client::{Dispatch, Proxy};
+use wayland_protocols_wlr::output_management::v1::client::{
+    zwlr_output_configuration_head_v1::ZwlrOutputConfigurationHeadV1,
+    zwlr_output_configuration_v1::ZwlrOutputConfigurationV1,
+};
+
+use super::{ConfigurationResult, OutputQueryState};
+
+impl Dispatch<ZwlrOutputConfigurationV1, ()> for OutputQueryState {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwlrOutputConfigurationV1,
+        event: <ZwlrOutputConfigurationV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &wayland_client::Connection,
+        _qhandle: &wayland_client::QueueHandle<Self>,
+    ) {
+        use wayland_protocols_wlr::output_management::v1::client::zwlr_output_configuration_v1::Event;
+        state.configuration_result = match event {
+            Event::Succeeded => Some(ConfigurationResult::Succeeded),
+            Event::Failed => Some(ConfigurationResult::Failed),
+            Event::Cancelled => Some(ConfigurationResult::Cancelled),
+            _ => return,
+        };
+        // Only this impl sets it: a manager `Done` racing with our own apply
+        // must never be able to end this dispatch loop before a result lands.
+        state.configuration_done = true;
+    }
+}
+
+impl Dispatch<ZwlrOutputConfigurationHeadV1, ()> for OutputQueryState {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ZwlrOutputConfigurationHeadV1,
+        event: <ZwlrOutputConfigurationHeadV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &wayland_client::Connection,
+        _qhandle: &wayland_client::QueueHandle<Self>,
+    ) {
+        debug!("Configuration head ignoring event {:?}", event);
+    }
+}
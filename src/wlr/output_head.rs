@@ -44,6 +44,30 @@ impl Dispatch<ZwlrOutputHeadV1, ()> for OutputQueryState {
                     warn!("Unknown head {:?}", proxy.id());
                 }
             }
+            Make { make } => {
+                let new_output = state.outputs.get_mut(&proxy.id()).map(|output| {
+                    output.make = make;
+                });
+                if new_output.is_none() {
+                    warn!("Unknown head {:?}", proxy.id());
+                }
+            }
+            Model { model } => {
+                let new_output = state.outputs.get_mut(&proxy.id()).map(|output| {
+                    output.model = model;
+                });
+                if new_output.is_none() {
+                    warn!("Unknown head {:?}", proxy.id());
+                }
+            }
+            SerialNumber { serial_number } => {
+                let new_output = state.outputs.get_mut(&proxy.id()).map(|output| {
+                    output.serial = serial_number;
+                });
+                if new_output.is_none() {
+                    warn!("Unknown head {:?}", proxy.id());
+                }
+            }
             Position { x, y } => {
                 let new_output = state.outputs.get_mut(&proxy.id()).map(|output| {
                     output.position = Some(super::Position { x, y });
@@ -52,6 +76,17 @@ impl Dispatch<ZwlrOutputHeadV1, ()> for OutputQueryState {
                     warn!("Unknown head {:?}", proxy.id());
                 }
             }
+            Enabled { enabled } => {
+                let new_output = state.outputs.get_mut(&proxy.id()).map(|output| {
+                    output.enabled = enabled != 0;
+                });
+                if new_output.is_none() {
+                    warn!("Unknown head {:?}", proxy.id());
+                }
+            }
+            CurrentMode { mode } => {
+                state.outputs_current_mode.insert(proxy.id(), mode.id());
+            }
             Mode { mode } => {
                 state.modes.insert(
                     mode.id(),
@@ -64,6 +99,7 @@ impl Dispatch<ZwlrOutputHeadV1, ()> for OutputQueryState {
                         preferred: false,
                     },
                 );
+                state.mode_proxies.insert(mode.id(), mode.clone());
                 let new_mode = state.output_to_modes.get_mut(&proxy.id()).map(|modes| {
                     modes.push(mode.id());
                 });
@@ -71,6 +107,14 @@ impl Dispatch<ZwlrOutputHeadV1, ()> for OutputQueryState {
                     warn!("Unknown head in mode assignment {:?}", proxy.id());
                 }
             }
+            Finished => {
+                // The head is gone (unplugged, or the compositor otherwise retired it).
+                // `WlrOutputManager` is long-lived now, so without this its maps would
+                // accumulate stale entries forever across a `--watch` session.
+                state.remove_head(&proxy.id());
+                proxy.release();
+                debug!("Head {:?} finished.", proxy.id());
+            }
             _ => debug!("Output head ignoring event {:?}", event),
         };
     }
@@ -25,8 +25,12 @@ impl Dispatch<ZwlrOutputManagerV1, ()> for OutputQueryState {
                 Output {
                     name: "unknown".into(),
                     description: String::new(),
+                    make: String::new(),
+                    model: String::new(),
+                    serial: String::new(),
                     position: None,
                     modes: Vec::new(),
+                    // Placeholder until the Enabled event lands, same as name/description above.
                     enabled: false,
                     current_mode: None,
                     preferred_mode: None,
@@ -34,8 +38,10 @@ impl Dispatch<ZwlrOutputManagerV1, ()> for OutputQueryState {
                 },
             );
             state.output_to_modes.insert(head.id(), Vec::new());
+            state.heads.insert(head.id(), head);
         } else if let Event::Done { serial } = event {
             trace!("Output manager done. {}", serial);
+            state.serial = serial;
             state.finalise();
         } else {
             warn!("Output manager ignored {:?}", event);
@@ -33,6 +33,14 @@ impl Dispatch<ZwlrOutputModeV1, ()> for OutputQueryState {
                     warn!("Unknown mode {:?}", proxy.id());
                 }
             }
+            Preferred => {
+                let new_mode = state.modes.get_mut(&proxy.id()).map(|mode| {
+                    mode.preferred = true;
+                });
+                if new_mode.is_none() {
+                    warn!("Unknown mode {:?}", proxy.id());
+                }
+            }
 
             _ => debug!("Mode ignoring event {:?}, {:?}", event, proxy.id()),
         }
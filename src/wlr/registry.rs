@@ -3,6 +3,13 @@ use wayland_client::{protocol::wl_registry, Dispatch};
 use wayland_protocols_wlr::output_management::v1::client::zwlr_output_manager_v1::ZwlrOutputManagerV1;
 
 use super::OutputQueryState;
+
+// Make/Model/SerialNumber were added to zwlr_output_head_v1 in version 2; binding at a
+// fixed version 1 silently withholds them even from a compositor that supports them.
+// The client may never bind higher than the compositor actually advertises, so we just
+// cap at the newest version we know how to speak.
+const MAX_SUPPORTED_MANAGER_VERSION: u32 = 4;
+
 impl Dispatch<wl_registry::WlRegistry, ()> for OutputQueryState {
     fn event(
         state: &mut Self,
@@ -13,13 +20,18 @@ impl Dispatch<wl_registry::WlRegistry, ()> for OutputQueryState {
         qhandle: &wayland_client::QueueHandle<Self>,
     ) {
         if let wl_registry::Event::Global {
-            name, interface, ..
+            name,
+            interface,
+            version,
         } = event
         {
             state.capabilities.push(interface.clone());
             if interface == "zwlr_output_manager_v1" {
-                info!("Binding output events.");
-                registry.bind::<ZwlrOutputManagerV1, _, _>(name, 1, qhandle, ());
+                let bind_version = version.min(MAX_SUPPORTED_MANAGER_VERSION);
+                info!("Binding output events at version {}.", bind_version);
+                let manager =
+                    registry.bind::<ZwlrOutputManagerV1, _, _>(name, bind_version, qhandle, ());
+                state.manager = Some(manager);
             }
         }
     }